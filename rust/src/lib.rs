@@ -4,7 +4,7 @@
 //! compiled to WebAssembly for use in JavaScript/TypeScript environments.
 
 #![no_std]
-#![cfg_attr(target_arch = "wasm32", feature(stdarch_wasm_atomic_wait))]
+#![cfg_attr(target_arch = "wasm32", feature(stdarch_wasm_atomic_wait, thread_local))]
 
 // Minimal Panic Handler
 #[cfg(not(test))]